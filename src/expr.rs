@@ -0,0 +1,278 @@
+//! Small expression evaluator used to parse the `value` and `--diff` arguments.
+//!
+//! Supports numeric literals (`0x`/`0X` hexadecimal, `0b`/`0B` binary, or
+//! decimal) combined with C-like arithmetic and bitwise operators. All
+//! arithmetic wraps on `u64`.
+//!
+//! Precedence, from tightest to loosest:
+//! unary `~`/`-`  >  `* / %`  >  `+ -`  >  `<< >>`  >  `&`  >  `^`  >  `|`
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(u64),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Shl,
+    Shr,
+    Amp,
+    Pipe,
+    Caret,
+    Tilde,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '+' => {
+                tokens.push(Token::Plus);
+                i += 1;
+            }
+            '-' => {
+                tokens.push(Token::Minus);
+                i += 1;
+            }
+            '*' => {
+                tokens.push(Token::Star);
+                i += 1;
+            }
+            '/' => {
+                tokens.push(Token::Slash);
+                i += 1;
+            }
+            '%' => {
+                tokens.push(Token::Percent);
+                i += 1;
+            }
+            '^' => {
+                tokens.push(Token::Caret);
+                i += 1;
+            }
+            '~' => {
+                tokens.push(Token::Tilde);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '<' if chars.get(i + 1) == Some(&'<') => {
+                tokens.push(Token::Shl);
+                i += 2;
+            }
+            '>' if chars.get(i + 1) == Some(&'>') => {
+                tokens.push(Token::Shr);
+                i += 2;
+            }
+            '&' => {
+                tokens.push(Token::Amp);
+                i += 1;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                i += 1;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                let radix = if c == '0' && matches!(chars.get(i + 1), Some('x' | 'X')) {
+                    i += 2;
+                    16
+                } else if c == '0' && matches!(chars.get(i + 1), Some('b' | 'B')) {
+                    i += 2;
+                    2
+                } else {
+                    10
+                };
+                let digits_start = i;
+                while chars.get(i).is_some_and(|c| c.is_digit(radix)) {
+                    i += 1;
+                }
+                if digits_start == i {
+                    return Err(format!("expected digits at position {start}"));
+                }
+                let digits: String = chars[digits_start..i].iter().collect();
+                let n = u64::from_str_radix(&digits, radix)
+                    .map_err(|_| format!("invalid number literal: {}", &digits))?;
+                tokens.push(Token::Num(n));
+            }
+            _ => return Err(format!("unexpected character '{c}'")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn bump(&mut self) -> Option<&Token> {
+        let tok = self.tokens.get(self.pos);
+        self.pos += 1;
+        tok
+    }
+
+    fn parse_expr(&mut self) -> Result<u64, String> {
+        let value = self.parse_or()?;
+        if self.pos != self.tokens.len() {
+            return Err("trailing characters after expression".into());
+        }
+        Ok(value)
+    }
+
+    fn parse_or(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_xor()?;
+        while let Some(Token::Pipe) = self.peek() {
+            self.bump();
+            lhs |= self.parse_xor()?;
+        }
+        Ok(lhs)
+    }
+
+    fn parse_xor(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_and()?;
+        while let Some(Token::Caret) = self.peek() {
+            self.bump();
+            lhs ^= self.parse_and()?;
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_shift()?;
+        while let Some(Token::Amp) = self.peek() {
+            self.bump();
+            lhs &= self.parse_shift()?;
+        }
+        Ok(lhs)
+    }
+
+    fn parse_shift(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_additive()?;
+        loop {
+            match self.peek() {
+                Some(Token::Shl) => {
+                    self.bump();
+                    let rhs = self.parse_additive()?;
+                    lhs = lhs.wrapping_shl(rhs as u32);
+                }
+                Some(Token::Shr) => {
+                    self.bump();
+                    let rhs = self.parse_additive()?;
+                    lhs = lhs.wrapping_shr(rhs as u32);
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_additive(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_multiplicative()?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.bump();
+                    lhs = lhs.wrapping_add(self.parse_multiplicative()?);
+                }
+                Some(Token::Minus) => {
+                    self.bump();
+                    lhs = lhs.wrapping_sub(self.parse_multiplicative()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<u64, String> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.bump();
+                    lhs = lhs.wrapping_mul(self.parse_unary()?);
+                }
+                Some(Token::Slash) => {
+                    self.bump();
+                    let rhs = self.parse_unary()?;
+                    if rhs == 0 {
+                        return Err("division by zero".into());
+                    }
+                    lhs = lhs.wrapping_div(rhs);
+                }
+                Some(Token::Percent) => {
+                    self.bump();
+                    let rhs = self.parse_unary()?;
+                    if rhs == 0 {
+                        return Err("division by zero".into());
+                    }
+                    lhs = lhs.wrapping_rem(rhs);
+                }
+                _ => break,
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<u64, String> {
+        match self.peek() {
+            Some(Token::Tilde) => {
+                self.bump();
+                Ok(!self.parse_unary()?)
+            }
+            Some(Token::Minus) => {
+                self.bump();
+                Ok(self.parse_unary()?.wrapping_neg())
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<u64, String> {
+        match self.bump() {
+            Some(Token::Num(n)) => Ok(*n),
+            Some(Token::LParen) => {
+                let value = self.parse_or()?;
+                match self.bump() {
+                    Some(Token::RParen) => Ok(value),
+                    _ => Err("expected closing parenthesis".into()),
+                }
+            }
+            other => Err(format!("unexpected token: {other:?}")),
+        }
+    }
+}
+
+/// Evaluate an arithmetic/bitwise expression of u64 literals, wrapping on overflow.
+pub fn eval(input: &str) -> Result<u64, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("empty expression".into());
+    }
+    Parser::new(&tokens).parse_expr()
+}
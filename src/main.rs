@@ -1,5 +1,8 @@
-use clap::{builder::TypedValueParser, error::ErrorKind, Parser, ValueEnum};
-use std::{collections::HashSet, hash::Hash, marker::PhantomData};
+use anstyle::{AnsiColor, Style};
+use clap::{builder::TypedValueParser, error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use std::{collections::HashSet, hash::Hash, io::Read, marker::PhantomData};
+
+mod expr;
 
 #[derive(ValueEnum, Clone, Copy, Hash, PartialEq, Eq, Debug)]
 #[repr(u64)]
@@ -7,75 +10,404 @@ enum Representation {
     B,
     D,
     H,
+    F,
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
-#[repr(u64)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    /// Apply this choice as the global color policy for every `anstream::print!`/`println!` call.
+    fn apply(self) {
+        match self {
+            Color::Auto => anstream::ColorChoice::Auto,
+            Color::Always => anstream::ColorChoice::Always,
+            Color::Never => anstream::ColorChoice::Never,
+        }
+        .write_global();
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
 enum Format {
-    U32 = 32,
-    U64 = 64,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+impl Format {
+    /// Number of significant bits for this format.
+    fn width(self) -> u32 {
+        match self {
+            Format::U8 | Format::I8 => 8,
+            Format::U16 | Format::I16 => 16,
+            Format::U32 | Format::I32 => 32,
+            Format::U64 | Format::I64 => 64,
+        }
+    }
+
+    fn signed(self) -> bool {
+        matches!(self, Format::I8 | Format::I16 | Format::I32 | Format::I64)
+    }
+
+    /// Bitmask covering exactly `width()` low bits.
+    fn mask(self) -> u64 {
+        let width = self.width();
+        if width == 64 {
+            u64::MAX
+        } else {
+            (1u64 << width) - 1
+        }
+    }
+}
+
+/// Reinterpret the low `width` bits of `value` as a two's-complement signed integer.
+fn to_signed(value: u64, width: u32) -> i64 {
+    let shift = 64 - width;
+    ((value << shift) as i64) >> shift
+}
+
+/// Bit layout of an IEEE-754 float for the given format width: (sign bit
+/// index, exponent width in bits, mantissa width in bits, exponent bias).
+fn float_layout(width: u32) -> (u32, u32, u32, i64) {
+    if width == 64 {
+        (63, 11, 52, 1023)
+    } else {
+        (31, 8, 23, 127)
+    }
+}
+
+fn float_kind(exponent: u64, mantissa: u64, exp_bits: u32) -> &'static str {
+    let exp_max = (1u64 << exp_bits) - 1;
+    if exponent == exp_max {
+        if mantissa == 0 {
+            "Inf"
+        } else {
+            "NaN"
+        }
+    } else if exponent == 0 {
+        "subnormal"
+    } else {
+        "normal"
+    }
+}
+
+fn print_field_row(hi: u32, lo: u32, width: u32) {
+    let (sign_bit, exp_bits, _mantissa_bits, _bias) = float_layout(width);
+    let exp_lo = sign_bit - exp_bits;
+    for i in (lo..=hi).rev() {
+        let c = if i == sign_bit {
+            'S'
+        } else if i >= exp_lo {
+            'E'
+        } else {
+            'M'
+        };
+        print!("{c}");
+        if i % 4 == 0 {
+            print!(" ")
+        }
+    }
+}
+
+fn display_float_detailed(value: u64, width: u32) {
+    let (sign_bit, exp_bits, mantissa_bits, bias) = float_layout(width);
+    let sign = (value >> sign_bit) & 1;
+    let exponent = (value >> (sign_bit - exp_bits)) & ((1 << exp_bits) - 1);
+    let mantissa = value & ((1u64 << mantissa_bits) - 1);
+    let kind = float_kind(exponent, mantissa, exp_bits);
+
+    if width == 64 {
+        println!("f64: {}", f64::from_bits(value));
+    } else {
+        println!("f32: {}", f32::from_bits(value as u32));
+    }
+
+    println!(
+        "sign: {sign}   exponent: {exponent:0ewidth$b} (unbiased {})   mantissa: {mantissa:0mwidth$b}  [{kind}]",
+        exponent as i64 - bias,
+        ewidth = exp_bits as usize,
+        mwidth = mantissa_bits as usize,
+    );
+
+    print_bit_row(value, sign_bit, 0, None);
+    println!();
+    print_field_row(sign_bit, 0, width);
+    println!();
+}
+
+/// Print the bits of `value` from `hi` down to `lo`, colorizing set bits and
+/// dimming the nibble separators. When `other` is given (bit-diff mode),
+/// bits that differ from `other` get a separate highlight, which takes
+/// precedence over the set-bit one.
+fn print_bit_row(value: u64, hi: u32, lo: u32, other: Option<u64>) {
+    let set_style = Style::new().fg_color(Some(AnsiColor::Green.into())).bold();
+    let diff_style = Style::new().fg_color(Some(AnsiColor::Red.into())).bold();
+    let sep_style = Style::new().dimmed();
+
+    for i in (lo..=hi).rev() {
+        let bit = value & 1 << i != 0;
+        let changed = other.is_some_and(|other| (other & 1 << i != 0) != bit);
+        let c = if bit { '1' } else { '0' };
+
+        let style = if changed {
+            diff_style
+        } else if bit {
+            set_style
+        } else {
+            Style::new()
+        };
+        anstream::print!("{style}{c}{style:#}");
+
+        if i % 4 == 0 {
+            anstream::print!("{sep_style} {sep_style:#}");
+        }
+    }
+}
+
+fn print_bit_legend(hi: u32, lo: u32) {
+    let mut i = hi - 3;
+    loop {
+        print!(" {i:4}");
+        if i == lo {
+            break;
+        }
+        i -= 4;
+    }
 }
 
 fn display_detailed(value: u64, repr: Representation, format: Format) {
     match repr {
         Representation::B => {
             print!("bin: ");
+            let width = format.width();
 
-            if let Format::U64 = format {
-                for i in (32..=63).rev() {
-                    if value & 1 << i != 0 {
-                        print!("1")
-                    } else {
-                        print!("0")
-                    }
-                    if i % 4 == 0 {
-                        print!(" ")
-                    }
-                }
+            if width > 32 {
+                print_bit_row(value, width - 1, 32, None);
+                println!();
+                print_bit_legend(width - 1, 32);
                 println!();
-                println!("       60   56   52   48   44   40   36   32");
                 print!("     ");
             }
 
-            for i in (0..=31).rev() {
-                if value & 1 << i != 0 {
-                    print!("1")
-                } else {
-                    print!("0")
-                }
-                if i % 4 == 0 {
-                    print!(" ")
-                }
-            }
+            print_bit_row(value, width.min(32) - 1, 0, None);
+            println!();
+            print_bit_legend(width.min(32) - 1, 0);
             println!();
-            println!("       28   24   20   16   12    8    4    0");
         }
         Representation::D => {
-            println!("dec: {value:39}")
+            if format.signed() {
+                println!("dec: {:39}", to_signed(value, format.width()))
+            } else {
+                println!("dec: {value:39}")
+            }
         }
         Representation::H => {
             println!("hex: {value:39x}")
         }
+        Representation::F => {
+            require_float_width(format.width());
+            display_float_detailed(value, format.width());
+        }
     }
 }
 
-fn display_simplified(value: u64, repr: Representation, format: Format) {
-    match repr {
-        Representation::B => (0..format as u64).rev().for_each(|i| {
-            let c = if value & 1 << i != 0 { '1' } else { '0' };
-            print!("{c}");
-        }),
-        Representation::D => print!("{value}"),
-        Representation::H => print!("{value:x}"),
+/// IEEE-754 layouts only exist for 32- and 64-bit widths; reject anything else
+/// instead of silently reinterpreting padding bits as exponent/mantissa.
+fn require_float_width(width: u32) {
+    if width != 32 && width != 64 {
+        AppConfig::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!(
+                    "representation 'f' (float reinterpretation) requires a 32- or 64-bit width, not {width}"
+                ),
+            )
+            .exit();
+    }
+}
+
+/// Render `value` and `other` stacked in binary, highlighting bit positions
+/// where they differ.
+fn display_diff(value: u64, other: u64, format: Format) {
+    let width = format.width();
+
+    if width > 32 {
+        print!("a:   ");
+        print_bit_row(value, width - 1, 32, Some(other));
+        println!();
+        print!("b:   ");
+        print_bit_row(other, width - 1, 32, Some(value));
+        println!();
+        print_bit_legend(width - 1, 32);
+        println!();
+        println!();
     }
+
+    print!("a:   ");
+    print_bit_row(value, width.min(32) - 1, 0, Some(other));
+    println!();
+    print!("b:   ");
+    print_bit_row(other, width.min(32) - 1, 0, Some(value));
     println!();
+    print_bit_legend(width.min(32) - 1, 0);
+    println!();
+}
+
+/// Render `value` in a single representation, assuming it only occupies `width` bits.
+fn format_simplified(value: u64, repr: Representation, width: u32, signed: bool) -> String {
+    match repr {
+        Representation::B => (0..width as u64)
+            .rev()
+            .map(|i| if value & 1 << i != 0 { '1' } else { '0' })
+            .collect(),
+        Representation::D => {
+            if signed {
+                to_signed(value, width).to_string()
+            } else {
+                value.to_string()
+            }
+        }
+        Representation::H => format!("{value:x}"),
+        Representation::F => {
+            require_float_width(width);
+            if width == 64 {
+                f64::from_bits(value).to_string()
+            } else {
+                f32::from_bits(value as u32).to_string()
+            }
+        }
+    }
+}
+
+fn display_simplified(value: u64, repr: Representation, format: Format) {
+    println!(
+        "{}",
+        format_simplified(value, repr, format.width(), format.signed())
+    );
+}
+
+/// A single `name=hi:lo` bit range parsed from `--fields`.
+#[derive(Clone, Debug)]
+struct Field {
+    name: String,
+    hi: u32,
+    lo: u32,
+}
+
+fn extract_field(value: u64, field: &Field) -> u64 {
+    let width = field.hi - field.lo + 1;
+    let mask = if width == 64 {
+        u64::MAX
+    } else {
+        (1u64 << width) - 1
+    };
+    (value >> field.lo) & mask
+}
+
+/// Warn (non-fatally) about overlapping or uncovered bits, and reject fields
+/// that fall outside the active format's width or, when `f` is among the
+/// requested representations, that aren't 32 or 64 bits wide.
+fn validate_fields(fields: &[Field], width: u32, representation: &HashSet<Representation>) {
+    for field in fields {
+        if field.hi >= width {
+            AppConfig::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "field '{}' ({}:{}) is out of range for a {width}-bit format",
+                        field.name, field.hi, field.lo
+                    ),
+                )
+                .exit();
+        }
+
+        if representation.contains(&Representation::F) {
+            let field_width = field.hi - field.lo + 1;
+            if field_width != 32 && field_width != 64 {
+                AppConfig::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!(
+                            "field '{}' is {field_width} bits wide: representation 'f' (float reinterpretation) requires a 32- or 64-bit field",
+                            field.name
+                        ),
+                    )
+                    .exit();
+            }
+        }
+    }
+
+    for (i, a) in fields.iter().enumerate() {
+        for b in &fields[i + 1..] {
+            if a.lo <= b.hi && b.lo <= a.hi {
+                eprintln!(
+                    "warning: field '{}' ({}:{}) overlaps field '{}' ({}:{})",
+                    a.name, a.hi, a.lo, b.name, b.hi, b.lo
+                );
+            }
+        }
+    }
+
+    let mut covered = vec![false; width as usize];
+    for field in fields {
+        for bit in field.lo..=field.hi {
+            covered[bit as usize] = true;
+        }
+    }
+    let uncovered: Vec<u32> = (0..width).filter(|&bit| !covered[bit as usize]).collect();
+    if !uncovered.is_empty() {
+        eprintln!("warning: bits not covered by any field: {uncovered:?}");
+    }
+}
+
+fn display_fields(value: u64, fields: &[Field], representation: &HashSet<Representation>) {
+    for field in fields {
+        let extracted = extract_field(value, field);
+        let width = field.hi - field.lo + 1;
+        let parts: Vec<String> = representation
+            .iter()
+            .map(|repr| format_simplified(extracted, *repr, width, false))
+            .collect();
+
+        println!(
+            "{:<10} {:>2}:{:<2} = {}",
+            field.name,
+            field.hi,
+            field.lo,
+            parts.join("  ")
+        );
+    }
+}
+
+/// Wrapper around the `--fields` list. `clap` treats a bare `Vec<T>`-typed
+/// field as "one `T` per occurrence", which conflicts with `FieldsParser`
+/// producing the whole list from a single occurrence; wrapping it in a
+/// newtype keeps the arg scalar from `clap`'s point of view.
+#[derive(Clone, Debug)]
+struct Fields(Vec<Field>);
+
+impl std::ops::Deref for Fields {
+    type Target = [Field];
+    fn deref(&self) -> &[Field] {
+        &self.0
+    }
 }
 
 #[derive(Clone)]
-struct MultiReprU64Parser;
+struct FieldsParser;
 
-impl TypedValueParser for MultiReprU64Parser {
-    type Value = u64;
+impl TypedValueParser for FieldsParser {
+    type Value = Fields;
     fn parse_ref(
         &self,
         cmd: &clap::Command,
@@ -86,16 +418,26 @@ impl TypedValueParser for MultiReprU64Parser {
             .to_str()
             .ok_or_else(|| clap::Error::new(ErrorKind::InvalidUtf8).with_cmd(cmd))?;
 
-        let value = if value.starts_with("0b") {
-            u64::from_str_radix(&value[2..], 2)
-        } else if value.starts_with("0x") {
-            u64::from_str_radix(&value[2..], 16)
-        } else {
-            u64::from_str_radix(&value, 10)
-        }
-        .map_err(|_| clap::Error::new(ErrorKind::InvalidValue).with_cmd(cmd))?;
+        let invalid = || clap::Error::new(ErrorKind::InvalidValue).with_cmd(cmd);
 
-        Ok(value)
+        value
+            .split(',')
+            .map(|part| {
+                let (name, range) = part.split_once('=').ok_or_else(invalid)?;
+                let (hi, lo) = range.split_once(':').ok_or_else(invalid)?;
+                let hi: u32 = hi.parse().map_err(|_| invalid())?;
+                let lo: u32 = lo.parse().map_err(|_| invalid())?;
+                if lo > hi {
+                    return Err(invalid());
+                }
+                Ok(Field {
+                    name: name.to_owned(),
+                    hi,
+                    lo,
+                })
+            })
+            .collect::<Result<Vec<Field>, clap::Error>>()
+            .map(Fields)
     }
 }
 
@@ -143,7 +485,8 @@ struct AppConfig {
     #[arg(
         short,
         long,
-        help = "Show 64 bits for the binary form instead of 32",
+        help = "Width and signedness of the value",
+        long_help = "Width and signedness of the value: u8, u16, u32, u64, i8, i16, i32 or i64. Signed formats are displayed as their two's-complement interpretation in decimal.",
         default_value = "u32"
     )]
     format: Format,
@@ -153,7 +496,7 @@ struct AppConfig {
         long = "repr",
         value_parser = EnumSetParser::<Representation>::new(','),
         help = "Representations to be printed",
-        long_help = "Possible values are h for hex, d for decimal, b for binary. Put multiple between commas to print multiple representations.",
+        long_help = "Possible values are h for hex, d for decimal, b for binary, f for IEEE-754 float reinterpretation. Put multiple between commas to print multiple representations.",
         default_value = "h,d,b"
     )]
     representation: HashSet<Representation>,
@@ -165,17 +508,124 @@ struct AppConfig {
     )]
     simplified: bool,
 
-    #[arg(value_parser=MultiReprU64Parser, help = "The number to convert (prefixed with 0x if hexadecimal, or 0b of binary)")]
-    value: u64,
+    #[arg(
+        long,
+        default_value = "auto",
+        help = "Whether to colorize the detailed binary view"
+    )]
+    color: Color,
+
+    #[arg(
+        long,
+        value_parser = FieldsParser,
+        help = "Decode the value into named bit fields instead of the normal representations",
+        long_help = "Comma-separated list of name=hi:lo bit ranges, e.g. \"opcode=31:26,rs=25:21,rt=20:16,imm=15:0\". Each field is extracted as (value >> lo) & mask(hi - lo + 1) and printed per selected representation."
+    )]
+    fields: Option<Fields>,
+
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "A second value (or expression) to diff against, highlighting changed bits in the binary view"
+    )]
+    diff: Option<String>,
+
+    #[arg(
+        allow_hyphen_values = true,
+        help = "The number to convert, or a C-like arithmetic/bitwise expression (e.g. \"(0xFF & 0b1010) << 4 | 3\"). Omit, or pass -, to read whitespace-separated values from stdin"
+    )]
+    value: Option<String>,
+}
+
+/// The raw value tokens to process: either the single positional argument,
+/// or every whitespace-separated token read from stdin when it is absent or `-`.
+fn input_tokens(value: Option<&str>) -> Vec<String> {
+    match value {
+        Some(value) if value != "-" => vec![value.to_owned()],
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .unwrap_or_else(|e| AppConfig::command().error(ErrorKind::Io, e).exit());
+            buf.split_whitespace().map(str::to_owned).collect()
+        }
+    }
+}
+
+/// Evaluate `raw` and make sure it fits `format`'s width, exiting with a clap
+/// error otherwise. The evaluator works in full 64-bit wrapping arithmetic, so
+/// a negative literal like `-5` carries its sign out to bit 63 rather than
+/// being confined to the target width; a value fits if the bits above the
+/// width are either all zero (it's a plain in-range literal) or, for signed
+/// formats, all ones matching the sign bit of the masked value (it's a
+/// faithful 64-bit sign extension). Anything else, such as `300` under `u8`,
+/// is a genuine overflow and is rejected.
+fn parse_and_validate(raw: &str, format: Format) -> u64 {
+    let value = expr::eval(raw).unwrap_or_else(|msg| {
+        AppConfig::command()
+            .error(ErrorKind::InvalidValue, msg)
+            .exit()
+    });
+
+    let width = format.width();
+    if width < 64 {
+        let high = value >> width;
+        let sign_extended = format.signed()
+            && high == u64::MAX >> width
+            && (value >> (width - 1)) & 1 == 1;
+        if high != 0 && !sign_extended {
+            AppConfig::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    format!(
+                        "{raw}: value does not fit in {width} bits (pass a narrower value or a wider --format)"
+                    ),
+                )
+                .exit();
+        }
+    }
+
+    value & format.mask()
 }
 
 fn main() {
     let cfg = AppConfig::parse();
-    for repr in cfg.representation {
-        if cfg.simplified {
-            display_simplified(cfg.value, repr, cfg.format);
-        } else {
-            display_detailed(cfg.value, repr, cfg.format);
+    cfg.color.apply();
+
+    if let Some(fields) = &cfg.fields {
+        validate_fields(fields, cfg.format.width(), &cfg.representation);
+    } else if cfg.representation.contains(&Representation::F) {
+        require_float_width(cfg.format.width());
+    }
+
+    let diff_value = cfg
+        .diff
+        .as_deref()
+        .map(|raw| parse_and_validate(raw, cfg.format));
+
+    for (i, raw) in input_tokens(cfg.value.as_deref()).iter().enumerate() {
+        let value = parse_and_validate(raw, cfg.format);
+
+        if i > 0 && !cfg.simplified {
+            println!();
+        }
+
+        if let Some(other) = diff_value {
+            display_diff(value, other, cfg.format);
+            continue;
+        }
+
+        if let Some(fields) = &cfg.fields {
+            display_fields(value, fields, &cfg.representation);
+            continue;
+        }
+
+        for repr in &cfg.representation {
+            if cfg.simplified {
+                display_simplified(value, *repr, cfg.format);
+            } else {
+                display_detailed(value, *repr, cfg.format);
+            }
         }
     }
 }